@@ -11,6 +11,11 @@ use anyhow::Result;
 use futures::stream::FuturesUnordered;
 use futures::stream::StreamExt;
 use parking_lot::Mutex;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::fmt;
 use std::sync::Arc;
@@ -20,6 +25,8 @@ pub enum ModuleGraphError {
   LoadingErr(anyhow::Error),
   ParseErr(ast::Diagnostic),
   InvalidSource(ModuleSpecifier),
+  Missing(ModuleSpecifier),
+  EmitErr(ModuleSpecifier, anyhow::Error),
 }
 
 impl std::error::Error for ModuleGraphError {}
@@ -39,6 +46,12 @@ impl fmt::Display for ModuleGraphError {
       Self::InvalidSource(specifier) => {
         format!("The source code is invalid, as it does not match the expected hash in the lock file.\n  Specifier: {}", specifier)
       }
+      Self::Missing(specifier) => {
+        format!("Module not found \"{}\".", specifier)
+      }
+      Self::EmitErr(specifier, err) => {
+        format!("Could not emit module \"{}\": {}", specifier, err)
+      }
     };
     write!(f, "{}", msg)
   }
@@ -91,6 +104,40 @@ impl Resolved {
   }
 }
 
+/// A flattened, serializable view of a `Resolved`, suitable for handing to
+/// downstream consumers (e.g. a `deno info` style command) without exposing
+/// the internal `ResolutionError` type.
+#[derive(Debug, Serialize)]
+pub struct ResolvedInfo {
+  pub specifier: Option<ModuleSpecifier>,
+  pub error: Option<String>,
+  /// The span in the referring module this resolution came from, so a
+  /// `deno info`-style consumer or an editor can point at the import.
+  pub span: Option<ast::Span>,
+}
+
+impl From<&Resolved> for ResolvedInfo {
+  fn from(resolved: &Resolved) -> Self {
+    match resolved {
+      Resolved::Specifier(specifier, span) => Self {
+        specifier: Some(specifier.clone()),
+        error: None,
+        span: Some(span.clone()),
+      },
+      Resolved::Err(err, span) => Self {
+        specifier: None,
+        error: Some(format!("{:?}", err)),
+        span: Some(span.clone()),
+      },
+      Resolved::None => Self {
+        specifier: None,
+        error: None,
+        span: None,
+      },
+    }
+  }
+}
+
 #[derive(Debug, Default)]
 pub struct Dependency {
   maybe_code: Resolved,
@@ -98,9 +145,47 @@ pub struct Dependency {
   is_dynamic: bool,
 }
 
+/// A serializable view of a `Dependency`, keyed by the dependency's string
+/// specifier as it appeared in the referring module's source.
+#[derive(Debug, Serialize)]
+pub struct DependencyInfo {
+  pub specifier: String,
+  pub is_dynamic: bool,
+  pub code: ResolvedInfo,
+  #[serde(rename = "type")]
+  pub maybe_type: ResolvedInfo,
+}
+
+impl Dependency {
+  /// A serializable view of the code resolution, since the internal
+  /// `Resolved`/`ResolutionError` types aren't public.
+  pub fn maybe_code(&self) -> ResolvedInfo {
+    ResolvedInfo::from(&self.maybe_code)
+  }
+
+  /// A serializable view of the type resolution, since the internal
+  /// `Resolved`/`ResolutionError` types aren't public.
+  pub fn maybe_type(&self) -> ResolvedInfo {
+    ResolvedInfo::from(&self.maybe_type)
+  }
+
+  pub fn is_dynamic(&self) -> bool {
+    self.is_dynamic
+  }
+}
+
+/// The result of transpiling a module's source to JavaScript.
+#[derive(Debug, Clone)]
+pub struct Emit {
+  pub code: String,
+  pub maybe_source_map: Option<String>,
+}
+
 #[derive(Debug)]
 pub struct Module {
   dependencies: HashMap<String, Dependency>,
+  maybe_emit: Option<Emit>,
+  maybe_emit_hash: Option<u64>,
   maybe_types_dependency: Option<(String, Resolved)>,
   media_type: MediaType,
   source: String,
@@ -111,12 +196,98 @@ impl Module {
   pub fn new(specifier: ModuleSpecifier, source: String) -> Self {
     Self {
       dependencies: Default::default(),
+      maybe_emit: None,
+      maybe_emit_hash: None,
       maybe_types_dependency: None,
       media_type: MediaType::Unknown,
       source,
       specifier,
     }
   }
+
+  /// Build a serializable snapshot of this module, suitable for a
+  /// `deno info`-style report.
+  pub fn info(&self) -> ModuleInfo {
+    let mut dependencies: Vec<DependencyInfo> = self
+      .dependencies
+      .iter()
+      .map(|(specifier, dep)| DependencyInfo {
+        specifier: specifier.clone(),
+        is_dynamic: dep.is_dynamic,
+        code: ResolvedInfo::from(&dep.maybe_code),
+        maybe_type: ResolvedInfo::from(&dep.maybe_type),
+      })
+      .collect();
+    dependencies.sort_by(|a, b| a.specifier.cmp(&b.specifier));
+
+    ModuleInfo {
+      specifier: self.specifier.clone(),
+      media_type: self.media_type.to_string(),
+      size: self.source.len(),
+      dependencies,
+      maybe_types_dependency: self
+        .maybe_types_dependency
+        .as_ref()
+        .map(|(specifier, resolved)| {
+          (specifier.clone(), ResolvedInfo::from(resolved))
+        }),
+    }
+  }
+
+  pub fn specifier(&self) -> &ModuleSpecifier {
+    &self.specifier
+  }
+
+  pub fn media_type(&self) -> &MediaType {
+    &self.media_type
+  }
+
+  pub fn source(&self) -> &str {
+    &self.source
+  }
+
+  pub fn dependencies(&self) -> &HashMap<String, Dependency> {
+    &self.dependencies
+  }
+
+  /// A serializable view of the `X-TypeScript-Types`/triple-slash types
+  /// dependency, since the internal `Resolved`/`ResolutionError` types
+  /// aren't public.
+  pub fn maybe_types_dependency(&self) -> Option<(&str, ResolvedInfo)> {
+    self
+      .maybe_types_dependency
+      .as_ref()
+      .map(|(specifier, resolved)| {
+        (specifier.as_str(), ResolvedInfo::from(resolved))
+      })
+  }
+
+  /// The emitted JavaScript for this module, if it has been transpiled via
+  /// `ModuleGraph::transpile()`.
+  pub fn maybe_emit(&self) -> Option<&Emit> {
+    self.maybe_emit.as_ref()
+  }
+
+  fn emit_hash(source: &str, options: &ast::EmitOptions) -> u64 {
+    use std::hash::Hash;
+    use std::hash::Hasher;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    source.hash(&mut hasher);
+    format!("{:?}", options).hash(&mut hasher);
+    hasher.finish()
+  }
+}
+
+/// A serializable snapshot of a `Module`.
+#[derive(Debug, Serialize)]
+pub struct ModuleInfo {
+  pub specifier: ModuleSpecifier,
+  pub media_type: String,
+  /// The size, in bytes, of the module's source.
+  pub size: usize,
+  pub dependencies: Vec<DependencyInfo>,
+  #[serde(rename = "typesDependency")]
+  pub maybe_types_dependency: Option<(String, ResolvedInfo)>,
 }
 
 #[derive(Debug)]
@@ -127,10 +298,25 @@ enum ModuleSlot {
   Pending,
 }
 
+/// A synthetic root of the graph that isn't imported by any module, e.g. an
+/// ambient type library or a config file's `imports` list. Mirrors deno's
+/// `GraphImport`: a fixed referrer specifier with a set of dependencies that
+/// are loaded and tracked the same way a real module's dependencies are.
+#[derive(Debug)]
+pub struct GraphImport {
+  pub referrer: ModuleSpecifier,
+  pub dependencies: HashMap<String, Dependency>,
+}
+
 #[derive(Debug)]
 pub struct ModuleGraph {
   root: ModuleSpecifier,
   maybe_locker: Option<Arc<Mutex<dyn Locker>>>,
+  maybe_graph_import: Option<GraphImport>,
+  /// The raw `(referrer, imports)` used to build `maybe_graph_import`,
+  /// retained so `ModuleGraph::update()` can re-derive it after rebuilding
+  /// its `Builder` instead of silently dropping it.
+  maybe_imports: Option<(ModuleSpecifier, Vec<(String, bool)>)>,
   modules: HashMap<ModuleSpecifier, ModuleSlot>,
   redirects: HashMap<ModuleSpecifier, ModuleSpecifier>,
 }
@@ -143,6 +329,8 @@ impl ModuleGraph {
     Self {
       root,
       maybe_locker,
+      maybe_graph_import: None,
+      maybe_imports: None,
       modules: Default::default(),
       redirects: Default::default(),
     }
@@ -167,6 +355,427 @@ impl ModuleGraph {
     }
     Ok(())
   }
+
+  /// Build a fresh `Lockfile` reflecting the current state of the graph: the
+  /// SHA-256 digest of every successfully loaded module's source, plus the
+  /// redirects that were followed while building the graph.
+  pub fn lockfile(&self) -> Lockfile {
+    let mut lockfile = Lockfile::default();
+    for module_slot in self.modules.values() {
+      if let ModuleSlot::Module(module) = module_slot {
+        lockfile
+          .remote
+          .insert(module.specifier.to_string(), Lockfile::hash(&module.source));
+      }
+    }
+    for (from, to) in self.redirects.iter() {
+      lockfile.redirects.insert(from.to_string(), to.to_string());
+    }
+    lockfile
+  }
+
+  /// Build a serializable snapshot of the whole graph, suitable for a
+  /// `deno info`-style report.
+  pub fn info(&self) -> ModuleGraphInfo {
+    let mut modules: Vec<ModuleInfo> = self
+      .modules
+      .values()
+      .filter_map(|slot| match slot {
+        ModuleSlot::Module(module) => Some(module.info()),
+        _ => None,
+      })
+      .collect();
+    modules.sort_by(|a, b| a.specifier.cmp(&b.specifier));
+
+    ModuleGraphInfo {
+      root: self.root.clone(),
+      modules,
+      redirects: self.redirects.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+      graph_import: self.maybe_graph_import.as_ref().map(GraphImport::info),
+    }
+  }
+
+  /// Resolve a specifier to its post-redirect specifier, chasing the
+  /// `redirects` map until it reaches a specifier that isn't itself
+  /// redirected.
+  pub fn resolve(&self, specifier: &ModuleSpecifier) -> ModuleSpecifier {
+    let mut redirected_specifier = specifier;
+    let mut seen = std::collections::HashSet::new();
+    seen.insert(redirected_specifier.clone());
+    while let Some(s) = self.redirects.get(redirected_specifier) {
+      if !seen.insert(s.clone()) {
+        break;
+      }
+      redirected_specifier = s;
+    }
+    redirected_specifier.clone()
+  }
+
+  /// The graph's synthetic root imports, if any were supplied to the
+  /// `Builder`.
+  pub fn maybe_graph_import(&self) -> Option<&GraphImport> {
+    self.maybe_graph_import.as_ref()
+  }
+
+  /// Get a module from the graph, transparently following any redirects.
+  /// Returns `None` if the specifier was never part of the graph, is still
+  /// pending, or failed to load/parse.
+  pub fn get(&self, specifier: &ModuleSpecifier) -> Option<&Module> {
+    let specifier = self.resolve(specifier);
+    match self.modules.get(&specifier) {
+      Some(ModuleSlot::Module(module)) => Some(module),
+      _ => None,
+    }
+  }
+
+  /// Walk the graph from `root`, following both static and dynamic
+  /// dependency edges, and return an error for the first module that
+  /// failed to load or parse.
+  pub fn valid(&self) -> Result<(), ModuleGraphError> {
+    let mut seen = std::collections::HashSet::new();
+    self.validate(&self.root, &mut seen)
+  }
+
+  fn validate(
+    &self,
+    specifier: &ModuleSpecifier,
+    seen: &mut std::collections::HashSet<ModuleSpecifier>,
+  ) -> Result<(), ModuleGraphError> {
+    let specifier = self.resolve(specifier);
+    if !seen.insert(specifier.clone()) {
+      return Ok(());
+    }
+    match self.modules.get(&specifier) {
+      Some(ModuleSlot::Module(module)) => {
+        for dep_specifier in Self::module_edges(module) {
+          self.validate(&dep_specifier, seen)?;
+        }
+        Ok(())
+      }
+      Some(ModuleSlot::Err(err)) => {
+        Err(ModuleGraphError::LoadingErr(anyhow::anyhow!("{}", err)))
+      }
+      Some(ModuleSlot::Missing) | None => {
+        Err(ModuleGraphError::Missing(specifier))
+      }
+      Some(ModuleSlot::Pending) => Ok(()),
+    }
+  }
+
+  /// The specifiers a module reaches, whether via a code import, a type
+  /// import (e.g. `@deno-types`), or the module's `X-TypeScript-Types`/
+  /// triple-slash types dependency. Shared between
+  /// `validate()` and `reachable_specifiers()` so neither drifts from the
+  /// other about what counts as an edge in the graph.
+  fn module_edges(module: &Module) -> Vec<ModuleSpecifier> {
+    let mut edges = Vec::new();
+    for dep in module.dependencies.values() {
+      if let Resolved::Specifier(specifier, _) = &dep.maybe_code {
+        edges.push(specifier.clone());
+      }
+      if let Resolved::Specifier(specifier, _) = &dep.maybe_type {
+        edges.push(specifier.clone());
+      }
+    }
+    if let Some((_, Resolved::Specifier(specifier, _))) =
+      &module.maybe_types_dependency
+    {
+      edges.push(specifier.clone());
+    }
+    edges
+  }
+
+  fn get_module_slot(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Option<&ModuleSlot> {
+    let redirected_specifier = self.resolve(specifier);
+    self.modules.get(&redirected_specifier)
+  }
+
+  /// Replace the source of an already-present module, re-resolving its
+  /// dependencies and loading any newly-introduced specifiers, then sweep
+  /// away any module no longer reachable from `root` or from the graph's
+  /// synthetic imports. Intended for
+  /// editor/LSP use, where rebuilding the whole graph on every keystroke is
+  /// too slow.
+  pub async fn update(
+    &mut self,
+    specifier: &ModuleSpecifier,
+    new_source: String,
+    maybe_headers: Option<HashMap<String, String>>,
+    loader: Arc<Mutex<dyn Loader>>,
+    maybe_resolver: Option<Arc<Mutex<dyn Resolver>>>,
+  ) -> UpdateResult {
+    let specifier = self.resolve(&specifier.clone());
+    let before: std::collections::HashSet<ModuleSpecifier> =
+      self.modules.keys().cloned().collect();
+
+    let placeholder =
+      ModuleGraph::new(self.root.clone(), self.maybe_locker.clone());
+    let graph = std::mem::replace(self, placeholder);
+    let maybe_imports = graph.maybe_imports.clone();
+    let mut builder = Builder {
+      is_dynamic_root: false,
+      graph,
+      loader,
+      maybe_resolver,
+      maybe_imports,
+      pending: FuturesUnordered::new(),
+    };
+    // Re-resolve the synthetic root imports against the rebuilt `Builder` so
+    // they aren't left stale (or, if a caller constructed `Builder`/`graph`
+    // differently, silently dropped) across the rebuild.
+    builder.resolve_graph_import();
+
+    builder.visit(
+      specifier.clone(),
+      LoadResponse {
+        specifier,
+        maybe_headers,
+        content: new_source,
+      },
+    );
+    loop {
+      match builder.pending.next().await {
+        Some((specifier, Ok(Some(response)))) => {
+          builder.visit(specifier, response)
+        }
+        Some((specifier, Ok(None))) => {
+          builder.graph.modules.insert(specifier, ModuleSlot::Missing);
+        }
+        Some((specifier, Err(err))) => {
+          builder.graph.modules.insert(
+            specifier,
+            ModuleSlot::Err(ModuleGraphError::LoadingErr(err)),
+          );
+        }
+        _ => {}
+      }
+      if builder.pending.is_empty() {
+        break;
+      }
+    }
+    *self = builder.graph;
+
+    let reachable = self.reachable_specifiers();
+    self.modules.retain(|specifier, _| reachable.contains(specifier));
+    self.redirects.retain(|_, to| reachable.contains(to));
+
+    let after: std::collections::HashSet<ModuleSpecifier> =
+      self.modules.keys().cloned().collect();
+    UpdateResult {
+      added: after.difference(&before).cloned().collect(),
+      removed: before.difference(&after).cloned().collect(),
+    }
+  }
+
+  /// Collect every specifier reachable from `root`, following static and
+  /// dynamic code dependencies, type dependencies, and redirects. Also
+  /// treats every dependency of `maybe_graph_import` as reachable: that's
+  /// the entire point of a synthetic root (e.g. an ambient type library) —
+  /// it's reachable even though no module imports it.
+  fn reachable_specifiers(&self) -> std::collections::HashSet<ModuleSpecifier> {
+    let mut seen = std::collections::HashSet::new();
+    let mut stack = vec![self.root.clone()];
+    if let Some(graph_import) = &self.maybe_graph_import {
+      for dep in graph_import.dependencies.values() {
+        if let Resolved::Specifier(s, _) = &dep.maybe_code {
+          stack.push(s.clone());
+        }
+        if let Resolved::Specifier(s, _) = &dep.maybe_type {
+          stack.push(s.clone());
+        }
+      }
+    }
+    while let Some(specifier) = stack.pop() {
+      let specifier = self.resolve(&specifier);
+      if !seen.insert(specifier.clone()) {
+        continue;
+      }
+      if let Some(ModuleSlot::Module(module)) = self.modules.get(&specifier) {
+        stack.extend(Self::module_edges(module));
+      }
+    }
+    seen
+  }
+
+  /// Transpile every TypeScript/JSX module in the graph to JavaScript,
+  /// storing the emitted code and source map on each `Module`. The emit is
+  /// cached on a hash of `(source, options)`, so calling this again with the
+  /// same options after only some modules changed re-emits just those.
+  pub fn transpile(
+    &mut self,
+    options: &ast::EmitOptions,
+  ) -> Result<(), ModuleGraphError> {
+    for module_slot in self.modules.values_mut() {
+      let module = match module_slot {
+        ModuleSlot::Module(module) => module,
+        _ => continue,
+      };
+      if !matches!(
+        module.media_type,
+        MediaType::TypeScript | MediaType::Tsx | MediaType::Jsx
+      ) {
+        continue;
+      }
+      let hash = Module::emit_hash(&module.source, options);
+      if module.maybe_emit_hash == Some(hash) {
+        continue;
+      }
+      let parsed_module =
+        ast::parse(&module.specifier, &module.source, &module.media_type)?;
+      let (code, maybe_source_map) =
+        parsed_module.transpile(options).map_err(|err| {
+          ModuleGraphError::EmitErr(module.specifier.clone(), err)
+        })?;
+      module.maybe_emit = Some(Emit {
+        code,
+        maybe_source_map,
+      });
+      module.maybe_emit_hash = Some(hash);
+    }
+    Ok(())
+  }
+
+  /// Write the dependency tree rooted at `specifier` to `f`, in the same
+  /// style as `deno info`: a tree of dependencies with already-visited
+  /// specifiers marked with a trailing `*` instead of being walked again.
+  fn fmt_tree(
+    &self,
+    f: &mut fmt::Formatter<'_>,
+    specifier: &ModuleSpecifier,
+    prefix: &str,
+    is_last: bool,
+    seen: &mut std::collections::HashSet<ModuleSpecifier>,
+  ) -> fmt::Result {
+    let connector = if is_last { "└── " } else { "├── " };
+    let already_seen = seen.contains(specifier);
+    write!(f, "{}{}{}", prefix, connector, specifier)?;
+    if already_seen {
+      writeln!(f, " *")?;
+      return Ok(());
+    }
+    writeln!(f)?;
+    seen.insert(specifier.clone());
+
+    let child_prefix =
+      format!("{}{}", prefix, if is_last { "    " } else { "│   " });
+    if let Some(ModuleSlot::Module(module)) = self.get_module_slot(specifier) {
+      let mut deps: Vec<&ModuleSpecifier> = module
+        .dependencies
+        .values()
+        .filter_map(|dep| match &dep.maybe_code {
+          Resolved::Specifier(specifier, _) => Some(specifier),
+          _ => None,
+        })
+        .collect();
+      deps.sort();
+      let len = deps.len();
+      for (i, dep_specifier) in deps.into_iter().enumerate() {
+        self.fmt_tree(
+          f,
+          dep_specifier,
+          &child_prefix,
+          i == len - 1,
+          seen,
+        )?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// A serializable snapshot of a `GraphImport`'s synthetic dependencies.
+#[derive(Debug, Serialize)]
+pub struct GraphImportInfo {
+  pub referrer: ModuleSpecifier,
+  pub dependencies: Vec<DependencyInfo>,
+}
+
+impl GraphImport {
+  /// Build a serializable snapshot of this graph import, in the same shape
+  /// as `Module::info()`'s dependencies.
+  pub fn info(&self) -> GraphImportInfo {
+    let mut dependencies: Vec<DependencyInfo> = self
+      .dependencies
+      .iter()
+      .map(|(specifier, dep)| DependencyInfo {
+        specifier: specifier.clone(),
+        is_dynamic: dep.is_dynamic,
+        code: ResolvedInfo::from(&dep.maybe_code),
+        maybe_type: ResolvedInfo::from(&dep.maybe_type),
+      })
+      .collect();
+    dependencies.sort_by(|a, b| a.specifier.cmp(&b.specifier));
+
+    GraphImportInfo {
+      referrer: self.referrer.clone(),
+      dependencies,
+    }
+  }
+}
+
+/// A serializable snapshot of a `ModuleGraph`, intended as the data backing
+/// a `deno info`-style command.
+#[derive(Debug, Serialize)]
+pub struct ModuleGraphInfo {
+  pub root: ModuleSpecifier,
+  pub modules: Vec<ModuleInfo>,
+  pub redirects: BTreeMap<ModuleSpecifier, ModuleSpecifier>,
+  /// The graph's synthetic root imports (e.g. a config's `imports` list or
+  /// ambient type libraries), surfaced through the same shape as a real
+  /// module's dependencies.
+  pub graph_import: Option<GraphImportInfo>,
+}
+
+impl fmt::Display for ModuleGraph {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "{}", self.root)?;
+    let mut seen = std::collections::HashSet::new();
+    seen.insert(self.root.clone());
+    if let Some(ModuleSlot::Module(module)) = self.get_module_slot(&self.root)
+    {
+      let mut deps: Vec<&ModuleSpecifier> = module
+        .dependencies
+        .values()
+        .filter_map(|dep| match &dep.maybe_code {
+          Resolved::Specifier(specifier, _) => Some(specifier),
+          _ => None,
+        })
+        .collect();
+      deps.sort();
+      let len = deps.len();
+      for (i, dep_specifier) in deps.into_iter().enumerate() {
+        self.fmt_tree(f, dep_specifier, "", i == len - 1, &mut seen)?;
+      }
+    }
+    if let Some(graph_import) = &self.maybe_graph_import {
+      writeln!(f, "{}", graph_import.referrer)?;
+      let mut deps: Vec<&ModuleSpecifier> = graph_import
+        .dependencies
+        .values()
+        .filter_map(|dep| match &dep.maybe_code {
+          Resolved::Specifier(specifier, _) => Some(specifier),
+          _ => None,
+        })
+        .collect();
+      deps.sort();
+      let len = deps.len();
+      for (i, dep_specifier) in deps.into_iter().enumerate() {
+        self.fmt_tree(f, dep_specifier, "", i == len - 1, &mut seen)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// The specifiers added and removed from a `ModuleGraph` by a call to
+/// `ModuleGraph::update()`.
+#[derive(Debug, Default)]
+pub struct UpdateResult {
+  pub added: std::collections::HashSet<ModuleSpecifier>,
+  pub removed: std::collections::HashSet<ModuleSpecifier>,
 }
 
 pub(crate) struct Builder {
@@ -174,6 +783,9 @@ pub(crate) struct Builder {
   graph: ModuleGraph,
   loader: Arc<Mutex<dyn Loader>>,
   maybe_resolver: Option<Arc<Mutex<dyn Resolver>>>,
+  /// A synthetic referrer plus the `(specifier, is_type)` pairs of its
+  /// dependencies, e.g. the `imports` list from a deno config file.
+  maybe_imports: Option<(ModuleSpecifier, Vec<(String, bool)>)>,
   pending: FuturesUnordered<LoadFuture>,
 }
 
@@ -184,17 +796,51 @@ impl Builder {
     loader: Arc<Mutex<dyn Loader>>,
     maybe_resolver: Option<Arc<Mutex<dyn Resolver>>>,
     maybe_locker: Option<Arc<Mutex<dyn Locker>>>,
+    maybe_imports: Option<(ModuleSpecifier, Vec<(String, bool)>)>,
   ) -> Self {
     Self {
       is_dynamic_root,
       graph: ModuleGraph::new(root_specifier, maybe_locker),
       loader,
       maybe_resolver,
+      maybe_imports,
       pending: FuturesUnordered::new(),
     }
   }
 
+  /// Resolve `self.maybe_imports` (if any) into `self.graph.maybe_graph_import`,
+  /// and retain the raw `(referrer, imports)` on the graph itself so a later
+  /// call to `ModuleGraph::update()` can re-derive it after rebuilding.
+  fn resolve_graph_import(&mut self) {
+    if let Some((referrer, imports)) = self.maybe_imports.take() {
+      let mut dependencies = HashMap::new();
+      for (specifier, is_type) in imports.iter() {
+        let resolved = self.resolve_load(
+          specifier,
+          &referrer,
+          &ast::Range::default(),
+          false,
+        );
+        let dep = dependencies
+          .entry(specifier.clone())
+          .or_insert_with(Dependency::default);
+        if *is_type {
+          dep.maybe_type = resolved;
+        } else {
+          dep.maybe_code = resolved;
+        }
+      }
+      self.graph.maybe_graph_import = Some(GraphImport {
+        referrer: referrer.clone(),
+        dependencies,
+      });
+      self.graph.maybe_imports = Some((referrer, imports));
+    }
+  }
+
   pub async fn build(mut self) -> ModuleGraph {
+    self.resolve_graph_import();
+
     let root = self.graph.root.clone();
     self.load(&root, self.is_dynamic_root);
 
@@ -408,4 +1054,537 @@ impl Builder {
       };
     self.graph.modules.insert(specifier, module_slot);
   }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ImportMapJson {
+  #[serde(default)]
+  imports: HashMap<String, String>,
+  #[serde(default)]
+  scopes: HashMap<String, HashMap<String, String>>,
+}
+
+/// A `Resolver` backed by a parsed WHATWG-style import map: a top-level
+/// `imports` map plus referrer-scoped `scopes` maps, both supporting
+/// trailing-slash prefix remapping with longest-prefix-match semantics.
+#[derive(Debug)]
+pub struct ImportMapResolver {
+  base_url: ModuleSpecifier,
+  imports: HashMap<String, String>,
+  scopes: HashMap<String, HashMap<String, String>>,
+}
+
+impl ImportMapResolver {
+  /// Parse an import map document. `base_url` is the specifier the import
+  /// map itself was loaded from, used to resolve its (possibly relative)
+  /// targets, as well as its scope keys (e.g. `"/node_modules/"` is written
+  /// relative to the map's own location, not an absolute URL).
+  pub fn from_json(base_url: ModuleSpecifier, json: &str) -> Result<Self> {
+    let parsed: ImportMapJson = serde_json::from_str(json)?;
+    let mut scopes = HashMap::new();
+    for (scope, scoped_imports) in parsed.scopes {
+      scopes.insert(Self::resolve_scope(&scope, &base_url)?, scoped_imports);
+    }
+    Ok(Self {
+      base_url,
+      imports: parsed.imports,
+      scopes,
+    })
+  }
+
+  /// Resolve a scope key against `base_url`, the same way a mapping target
+  /// is resolved, and normalize it to end with a trailing slash so that, for
+  /// example, a scope key of `"/foo"` can't wrongly prefix-match a referrer
+  /// of `"/foobar/x.js"`.
+  fn resolve_scope(scope: &str, base_url: &ModuleSpecifier) -> Result<String> {
+    let mut resolved = resolve_import(scope, base_url)?.to_string();
+    if !resolved.ends_with('/') {
+      resolved.push('/');
+    }
+    Ok(resolved)
+  }
+
+  /// Look `specifier` up in `map`, first as an exact match, then as the
+  /// longest trailing-slash prefix it starts with.
+  fn resolve_in_map(
+    map: &HashMap<String, String>,
+    specifier: &str,
+  ) -> Option<Result<String>> {
+    if let Some(target) = map.get(specifier) {
+      return Some(Ok(target.clone()));
+    }
+    let best = map
+      .iter()
+      .filter(|(prefix, _)| {
+        prefix.ends_with('/') && specifier.starts_with(prefix.as_str())
+      })
+      .max_by_key(|(prefix, _)| prefix.len());
+    best.map(|(prefix, target)| {
+      if !target.ends_with('/') {
+        anyhow::bail!(
+          "Import map target \"{}\" for prefix \"{}\" must end with a slash.",
+          target,
+          prefix
+        );
+      }
+      Ok(format!("{}{}", target, &specifier[prefix.len()..]))
+    })
+  }
+}
+
+impl Resolver for ImportMapResolver {
+  fn resolve(
+    &self,
+    specifier: &str,
+    referrer: &ModuleSpecifier,
+  ) -> Result<ModuleSpecifier> {
+    // Scope keys were already resolved against `base_url` and normalized to
+    // a trailing slash in `from_json`, so this compares like for like
+    // against the referrer's own absolute URL. Per the import map spec, we
+    // try every matching scope from most to least specific, and only fall
+    // through to the next one if the specifier isn't mapped there.
+    let mut matching_scopes: Vec<(&String, &HashMap<String, String>)> = self
+      .scopes
+      .iter()
+      .filter(|(scope, _)| referrer.as_str().starts_with(scope.as_str()))
+      .collect();
+    matching_scopes.sort_by_key(|(scope, _)| std::cmp::Reverse(scope.len()));
+    for (_, scoped_imports) in matching_scopes {
+      if let Some(result) = Self::resolve_in_map(scoped_imports, specifier) {
+        return resolve_import(&result?, &self.base_url)
+          .map_err(|err| err.into());
+      }
+    }
+    if let Some(result) = Self::resolve_in_map(&self.imports, specifier) {
+      return resolve_import(&result?, &self.base_url)
+        .map_err(|err| err.into());
+    }
+    resolve_import(specifier, referrer).map_err(|err| err.into())
+  }
+}
+
+/// A deterministic, shareable lock artifact mapping the specifier of each
+/// loaded module (local ones included, all kept under `remote`) to the
+/// SHA-256 digest of its fetched source, along with the
+/// redirects that were followed to reach it. This is also the default
+/// `Locker` implementation: inserting a module's hash the first time it is
+/// seen and verifying it on every subsequent build.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Lockfile {
+  pub version: String,
+  pub remote: BTreeMap<String, String>,
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub redirects: BTreeMap<String, String>,
+}
+
+impl Default for Lockfile {
+  fn default() -> Self {
+    Self {
+      version: "2".to_string(),
+      remote: BTreeMap::new(),
+      redirects: BTreeMap::new(),
+    }
+  }
+}
+
+impl Lockfile {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Parse a v2-style lockfile document.
+  pub fn from_json(json: &str) -> Result<Self> {
+    Ok(serde_json::from_str(json)?)
+  }
+
+  /// Serialize to the v2-style lockfile document, with the `remote` map in
+  /// stable, sorted order.
+  pub fn to_json(&self) -> Result<String> {
+    Ok(serde_json::to_string_pretty(self)?)
+  }
+
+  fn hash(source: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(source.as_bytes());
+    format!("{:x}", hasher.finalize())
+  }
+}
+
+impl Locker for Lockfile {
+  /// Check the source against the previously recorded hash for `specifier`,
+  /// inserting it if this is the first time the specifier has been seen.
+  /// Returns `false` on a mismatch, matching the `Locker` contract that
+  /// backs `ModuleGraph::lock()`.
+  fn check_or_insert(&mut self, specifier: &ModuleSpecifier, source: &str) -> bool {
+    let hash = Self::hash(source);
+    match self.remote.get(specifier.as_str()) {
+      Some(existing) => existing == &hash,
+      None => {
+        self.remote.insert(specifier.to_string(), hash);
+        true
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn specifier(s: &str) -> ModuleSpecifier {
+    s.parse().unwrap()
+  }
+
+  /// Build a graph whose root module has a single code import to `dep`,
+  /// with the root module already inserted. Callers insert whatever
+  /// `ModuleSlot` they need for `dep` (a real module, `Missing`, etc.) to
+  /// exercise different `valid()`/`update()` scenarios.
+  fn graph_with_root_importing(
+    root: &ModuleSpecifier,
+    dep: &ModuleSpecifier,
+  ) -> ModuleGraph {
+    let mut graph = ModuleGraph::new(root.clone(), None);
+    let mut root_module =
+      Module::new(root.clone(), format!("import \"{}\";", dep));
+    let mut dependency = Dependency::default();
+    dependency.maybe_code = Resolved::Specifier(
+      dep.clone(),
+      ast::Span {
+        specifier: root.clone(),
+        range: ast::Range::default(),
+      },
+    );
+    root_module.dependencies.insert(dep.to_string(), dependency);
+    graph
+      .modules
+      .insert(root.clone(), ModuleSlot::Module(root_module));
+    graph
+  }
+
+  /// A `Loader` backed by an in-memory map, for exercising `Builder`/
+  /// `ModuleGraph::update()` without real network or file I/O.
+  struct MemoryLoader(HashMap<ModuleSpecifier, String>);
+
+  impl Loader for MemoryLoader {
+    fn load(
+      &mut self,
+      specifier: &ModuleSpecifier,
+      _is_dynamic: bool,
+    ) -> LoadFuture {
+      let specifier = specifier.clone();
+      let result = match self.0.get(&specifier) {
+        Some(source) => Ok(Some(LoadResponse {
+          specifier: specifier.clone(),
+          maybe_headers: None,
+          content: source.clone(),
+        })),
+        None => Ok(None),
+      };
+      Box::pin(async move { (specifier, result) })
+    }
+  }
+
+  #[test]
+  fn test_info_and_display_tree() {
+    let root = specifier("https://deno.land/x/a.ts");
+    let dep = specifier("https://deno.land/x/b.ts");
+
+    let mut graph = graph_with_root_importing(&root, &dep);
+    let dep_module =
+      Module::new(dep.clone(), "export const b = 1;".to_string());
+    graph.modules.insert(dep.clone(), ModuleSlot::Module(dep_module));
+
+    let info = graph.info();
+    assert_eq!(info.root, root);
+    assert_eq!(info.modules.len(), 2);
+
+    let rendered = graph.to_string();
+    assert!(rendered.contains(root.as_str()));
+    assert!(rendered.contains(dep.as_str()));
+  }
+
+  #[test]
+  fn test_valid_ok() {
+    let root = specifier("https://deno.land/x/a.ts");
+    let dep = specifier("https://deno.land/x/b.ts");
+
+    let mut graph = graph_with_root_importing(&root, &dep);
+    graph.modules.insert(
+      dep.clone(),
+      ModuleSlot::Module(Module::new(dep, "export const b = 1;".to_string())),
+    );
+
+    assert!(graph.valid().is_ok());
+    assert!(graph.get(&root).is_some());
+  }
+
+  #[test]
+  fn test_valid_reports_first_missing_dependency() {
+    let root = specifier("https://deno.land/x/a.ts");
+    let dep = specifier("https://deno.land/x/missing.ts");
+
+    let mut graph = graph_with_root_importing(&root, &dep);
+    graph.modules.insert(dep.clone(), ModuleSlot::Missing);
+
+    match graph.valid() {
+      Err(ModuleGraphError::Missing(specifier)) => assert_eq!(specifier, dep),
+      other => panic!("expected Missing error, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn test_get_follows_redirects() {
+    let root = specifier("https://deno.land/x/a.ts");
+    let redirected = specifier("https://deno.land/x/a_actual.ts");
+
+    let mut graph = ModuleGraph::new(root.clone(), None);
+    graph.redirects.insert(root.clone(), redirected.clone());
+    graph.modules.insert(
+      redirected.clone(),
+      ModuleSlot::Module(Module::new(redirected.clone(), "".to_string())),
+    );
+
+    assert_eq!(graph.resolve(&root), redirected);
+    assert!(graph.get(&root).is_some());
+  }
+
+  #[test]
+  fn test_transpile_caches_by_content_hash() {
+    let root = specifier("https://deno.land/x/a.ts");
+    let mut graph = ModuleGraph::new(root.clone(), None);
+    let mut module =
+      Module::new(root.clone(), "const a: number = 1;".to_string());
+    module.media_type = MediaType::TypeScript;
+    graph.modules.insert(root.clone(), ModuleSlot::Module(module));
+
+    let options = ast::EmitOptions::default();
+    graph.transpile(&options).unwrap();
+    let emit = graph.get(&root).unwrap().maybe_emit().unwrap().code.clone();
+    assert!(!emit.is_empty());
+
+    let hash_after_first = match graph.modules.get(&root) {
+      Some(ModuleSlot::Module(module)) => module.maybe_emit_hash,
+      _ => None,
+    };
+    assert!(hash_after_first.is_some());
+
+    // Re-transpiling with the same options is a no-op: the cached hash is
+    // unchanged since neither the source nor the options changed.
+    graph.transpile(&options).unwrap();
+    let hash_after_second = match graph.modules.get(&root) {
+      Some(ModuleSlot::Module(module)) => module.maybe_emit_hash,
+      _ => None,
+    };
+    assert_eq!(hash_after_first, hash_after_second);
+  }
+
+  #[test]
+  fn test_lockfile_round_trip_and_mismatch() {
+    let specifier = specifier("https://deno.land/x/a.ts");
+    let source = "export const a = 1;";
+
+    let mut lockfile = Lockfile::new();
+    assert!(lockfile.check_or_insert(&specifier, source));
+    assert_eq!(lockfile.version, "2");
+    assert_eq!(lockfile.remote.len(), 1);
+
+    // Serializing and re-parsing should produce an equal lockfile.
+    let json = lockfile.to_json().unwrap();
+    let reloaded = Lockfile::from_json(&json).unwrap();
+    assert_eq!(lockfile, reloaded);
+
+    // The same source verifies cleanly against the recorded hash.
+    let mut verifying = reloaded.clone();
+    assert!(verifying.check_or_insert(&specifier, source));
+
+    // A changed source fails the integrity check.
+    let mut verifying = reloaded;
+    assert!(!verifying.check_or_insert(&specifier, "export const a = 2;"));
+  }
+
+  #[test]
+  fn test_graph_lockfile_includes_redirects() {
+    let root = specifier("https://deno.land/x/a.ts");
+    let redirected = specifier("https://deno.land/x/a_actual.ts");
+
+    let mut graph = ModuleGraph::new(root.clone(), None);
+    graph.redirects.insert(root, redirected.clone());
+    graph.modules.insert(
+      redirected.clone(),
+      ModuleSlot::Module(Module::new(redirected.clone(), "export {};".to_string())),
+    );
+
+    let lockfile = graph.lockfile();
+    assert_eq!(lockfile.remote.len(), 1);
+    assert!(lockfile.remote.contains_key(redirected.as_str()));
+    assert_eq!(lockfile.redirects.len(), 1);
+  }
+
+  #[test]
+  fn test_update_drops_modules_no_longer_reachable() {
+    let root = specifier("https://deno.land/x/a.ts");
+    let dep = specifier("https://deno.land/x/b.ts");
+
+    let mut graph = graph_with_root_importing(&root, &dep);
+    graph.modules.insert(
+      dep.clone(),
+      ModuleSlot::Module(Module::new(dep.clone(), "export const b = 1;".to_string())),
+    );
+
+    // Editing the root to drop its only import should sweep `dep` away, as
+    // it's no longer reachable from anywhere.
+    let loader: Arc<Mutex<dyn Loader>> =
+      Arc::new(Mutex::new(MemoryLoader(HashMap::new())));
+    let result = futures::executor::block_on(graph.update(
+      &root,
+      "export const a = 1;".to_string(),
+      None,
+      loader,
+      None,
+    ));
+
+    assert!(result.removed.contains(&dep));
+    assert!(graph.get(&dep).is_none());
+    assert!(graph.get(&root).is_some());
+  }
+
+  #[test]
+  fn test_update_keeps_modules_reachable_only_through_graph_import() {
+    let root = specifier("https://deno.land/x/a.ts");
+    let ambient = specifier("https://deno.land/x/ambient.d.ts");
+    let synthetic_referrer = specifier("https://deno.land/x/deno.json");
+
+    let mut graph = ModuleGraph::new(root.clone(), None);
+    graph.modules.insert(
+      root.clone(),
+      ModuleSlot::Module(Module::new(root.clone(), "export const a = 1;".to_string())),
+    );
+    graph.modules.insert(
+      ambient.clone(),
+      ModuleSlot::Module(Module::new(ambient.clone(), "declare const x: string;".to_string())),
+    );
+    let mut graph_import_deps = HashMap::new();
+    let mut dependency = Dependency::default();
+    dependency.maybe_code = Resolved::Specifier(
+      ambient.clone(),
+      ast::Span {
+        specifier: synthetic_referrer.clone(),
+        range: ast::Range::default(),
+      },
+    );
+    graph_import_deps.insert(ambient.to_string(), dependency);
+    graph.maybe_graph_import = Some(GraphImport {
+      referrer: synthetic_referrer,
+      dependencies: graph_import_deps,
+    });
+
+    // `ambient` is not imported by `root` at all — it's only reachable
+    // through the synthetic graph import, which `reachable_specifiers()`
+    // must account for or `update()` would sweep it away.
+    let loader: Arc<Mutex<dyn Loader>> =
+      Arc::new(Mutex::new(MemoryLoader(HashMap::new())));
+    let result = futures::executor::block_on(graph.update(
+      &root,
+      "export const a = 2;".to_string(),
+      None,
+      loader,
+      None,
+    ));
+
+    assert!(!result.removed.contains(&ambient));
+    assert!(graph.get(&ambient).is_some());
+  }
+
+  #[test]
+  fn test_import_map_resolver_exact_prefix_and_scope() {
+    let base_url = specifier("https://deno.land/import_map.json");
+    let json = r#"{
+      "imports": {
+        "foo": "https://deno.land/x/foo/mod.ts",
+        "bar/": "https://deno.land/x/bar/"
+      },
+      "scopes": {
+        "/node_modules/": {
+          "foo": "https://deno.land/x/scoped_foo/mod.ts"
+        }
+      }
+    }"#;
+    let resolver = ImportMapResolver::from_json(base_url, json).unwrap();
+
+    let outside_scope = specifier("https://deno.land/x/referrer.ts");
+
+    // Exact match in the top-level `imports`.
+    assert_eq!(
+      resolver.resolve("foo", &outside_scope).unwrap(),
+      specifier("https://deno.land/x/foo/mod.ts")
+    );
+
+    // Trailing-slash prefix match in the top-level `imports`.
+    assert_eq!(
+      resolver.resolve("bar/a.ts", &outside_scope).unwrap(),
+      specifier("https://deno.land/x/bar/a.ts")
+    );
+
+    // A referrer inside the (base_url-resolved) scope takes precedence over
+    // the top-level mapping for the same bare specifier.
+    let inside_scope = specifier("https://deno.land/node_modules/x.ts");
+    assert_eq!(
+      resolver.resolve("foo", &inside_scope).unwrap(),
+      specifier("https://deno.land/x/scoped_foo/mod.ts")
+    );
+
+    // A referrer outside the scope still gets the top-level mapping.
+    assert_eq!(
+      resolver.resolve("foo", &outside_scope).unwrap(),
+      specifier("https://deno.land/x/foo/mod.ts")
+    );
+  }
+
+  #[test]
+  fn test_synthetic_graph_import_survives_build_and_update() {
+    let root = specifier("https://deno.land/x/a.ts");
+    let ambient = specifier("https://deno.land/x/ambient.d.ts");
+    let synthetic_referrer = specifier("https://deno.land/x/deno.json");
+
+    let mut sources = HashMap::new();
+    sources.insert(root.clone(), "export const a = 1;".to_string());
+    sources.insert(ambient.clone(), "declare const x: string;".to_string());
+    let loader: Arc<Mutex<dyn Loader>> =
+      Arc::new(Mutex::new(MemoryLoader(sources)));
+
+    let builder = Builder::new(
+      root.clone(),
+      false,
+      loader,
+      None,
+      None,
+      Some((synthetic_referrer.clone(), vec![(ambient.to_string(), true)])),
+    );
+    let mut graph = futures::executor::block_on(builder.build());
+
+    let graph_import =
+      graph.maybe_graph_import().expect("graph import to be set");
+    assert_eq!(graph_import.referrer, synthetic_referrer);
+    let dep = graph_import
+      .dependencies
+      .get(ambient.as_str())
+      .expect("ambient dependency");
+    assert_eq!(dep.maybe_type().specifier, Some(ambient.clone()));
+    assert!(graph.get(&ambient).is_some());
+
+    // Editing the root and re-running `update()` must not silently drop the
+    // synthetic graph import or the modules only reachable through it.
+    let loader: Arc<Mutex<dyn Loader>> =
+      Arc::new(Mutex::new(MemoryLoader(HashMap::new())));
+    futures::executor::block_on(graph.update(
+      &root,
+      "export const a = 2;".to_string(),
+      None,
+      loader,
+      None,
+    ));
+
+    assert!(graph.maybe_graph_import().is_some());
+    assert!(graph.get(&ambient).is_some());
+  }
 }
\ No newline at end of file